@@ -3,10 +3,11 @@ mod wrappers;
 use std::alloc::{dealloc, Layout};
 
 use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
 use ed25519_dalek::Verifier;
 use frost_dalek::{
     message_to_buffer, generate_commitment_share_lists,
-    keygen::{Coefficients, RoundOne},
+    keygen::{Coefficients, GroupKey, RoundOne},
     precomputation::SecretCommitmentShareList,
     signature::{Initial, PartialThresholdSignature},
     DistributedKeyGeneration, IndividualPublicKey, IndividualSecretKey, Parameters, Participant,
@@ -31,6 +32,78 @@ unsafe fn drop_handle<T>(handle: usize) {
     dealloc(handle as *mut u8, Layout::new::<T>());
 }
 
+// Checkpointing only compiles when frost_dalek is pulled in with its `serde`
+// feature enabled (Cargo.toml: `frost_dalek = { version = "...", features =
+// ["serde"] }`) -- that's what actually gives `Coefficients`,
+// `DistributedKeyGeneration<RoundOne>`, `SecretCommitmentShareList`, and
+// `SignatureAggregator<Initial>` their `Serialize`/`DeserializeOwned` impls.
+// Everything below is gated on this crate's `frost-serde` feature: a build
+// without it still succeeds, but none of the serialize/deserialize functions
+// are compiled in or exported.
+#[cfg(feature = "frost-serde")]
+fn serialize_handle<T: serde::Serialize>(handle: i64) -> Result<Buffer> {
+    let boxed: Box<T> = unsafe { from_handle(handle) };
+    let bytes = bincode::serialize(&*boxed)
+        .map_err(|e| Error::from_reason(format!("failed to serialize state: {}", e)));
+    std::mem::forget(boxed);
+    bytes.map(|b| b.into())
+}
+
+#[cfg(feature = "frost-serde")]
+fn deserialize_handle<T: serde::de::DeserializeOwned>(data: Buffer) -> Result<i64> {
+    let value: T = bincode::deserialize(&data)
+        .map_err(|e| Error::from_reason(format!("failed to deserialize state: {}", e)))?;
+    Ok(into_boxed_handle(value))
+}
+
+#[cfg(feature = "frost-serde")]
+#[napi]
+fn serialize_coefficients_state(coefficients_handle: i64) -> Result<Buffer> {
+    serialize_handle::<Coefficients>(coefficients_handle)
+}
+
+#[cfg(feature = "frost-serde")]
+#[napi]
+fn deserialize_coefficients_state(data: Buffer) -> Result<i64> {
+    deserialize_handle::<Coefficients>(data)
+}
+
+#[cfg(feature = "frost-serde")]
+#[napi]
+fn serialize_dkg_state(state_handle: i64) -> Result<Buffer> {
+    serialize_handle::<DistributedKeyGeneration<RoundOne>>(state_handle)
+}
+
+#[cfg(feature = "frost-serde")]
+#[napi]
+fn deserialize_dkg_state(data: Buffer) -> Result<i64> {
+    deserialize_handle::<DistributedKeyGeneration<RoundOne>>(data)
+}
+
+#[cfg(feature = "frost-serde")]
+#[napi]
+fn serialize_secret_comm_share_state(secret_comm_share_handle: i64) -> Result<Buffer> {
+    serialize_handle::<SecretCommitmentShareList>(secret_comm_share_handle)
+}
+
+#[cfg(feature = "frost-serde")]
+#[napi]
+fn deserialize_secret_comm_share_state(data: Buffer) -> Result<i64> {
+    deserialize_handle::<SecretCommitmentShareList>(data)
+}
+
+#[cfg(feature = "frost-serde")]
+#[napi]
+fn serialize_aggregator_state(aggregator_handle: i64) -> Result<Buffer> {
+    serialize_handle::<SignatureAggregator<Initial>>(aggregator_handle)
+}
+
+#[cfg(feature = "frost-serde")]
+#[napi]
+fn deserialize_aggregator_state(data: Buffer) -> Result<i64> {
+    deserialize_handle::<SignatureAggregator<Initial>>(data)
+}
+
 #[napi]
 fn participate(uuid: u32, num_sig: u32, threshold: u32) -> ParticipateRes {
     let params = Parameters {
@@ -132,8 +205,55 @@ fn derive_pubk_and_group_key(
 }
 
 #[napi]
-fn gen_commitment_share_lists(uuid: u32) -> GenCommitmentShareRes {
-    let (pub_comm_share, secret_comm) = generate_commitment_share_lists(&mut OsRng, uuid, 1);
+fn keygen_with_dealer(num_sig: u32, threshold: u32) -> Result<DealerKeygenRes> {
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+
+    if threshold == 0 {
+        return Err(Error::from_reason::<String>(
+            "threshold must be at least 1".into(),
+        ));
+    }
+    if num_sig < threshold {
+        return Err(Error::from_reason::<String>(
+            "num_sig must be at least threshold".into(),
+        ));
+    }
+
+    let mut rng = OsRng;
+    // a_0..a_{t-1}: the single degree-(t-1) polynomial the dealer samples once.
+    let coefficients: Vec<Scalar> = (0..threshold).map(|_| Scalar::random(&mut rng)).collect();
+
+    let gk = GroupKey(&coefficients[0] * &RISTRETTO_BASEPOINT_TABLE);
+
+    let mut shares = Vec::with_capacity(num_sig as usize);
+    for i in 1..=num_sig {
+        let x = Scalar::from(i as u64);
+        let mut share = Scalar::zero();
+        for coeff in coefficients.iter().rev() {
+            share = share * x + coeff;
+        }
+
+        let secret_key = IndividualSecretKey {
+            index: i,
+            key: share,
+        };
+        shares.push(DealerShareRes {
+            index: i,
+            pubk: secret_key.to_public().into(),
+            sk: secret_key.into(),
+        });
+    }
+
+    Ok(DealerKeygenRes {
+        gk: gk.to_bytes().to_vec().into(),
+        shares,
+    })
+}
+
+#[napi]
+fn gen_commitment_share_lists(uuid: u32, number_of_shares: u32) -> GenCommitmentShareRes {
+    let (pub_comm_share, secret_comm) =
+        generate_commitment_share_lists(&mut OsRng, uuid, number_of_shares);
     GenCommitmentShareRes {
         public_comm_share: pub_comm_share.into(),
         secret_comm_share_handle: into_boxed_handle(secret_comm),
@@ -145,6 +265,11 @@ fn discard_secret_share_handle(handle: i64) {
     unsafe { drop_handle::<SecretShareWrapper>(handle as usize) };
 }
 
+#[napi]
+fn discard_secret_comm_share_handle(handle: i64) {
+    unsafe { drop_handle::<SecretCommitmentShareList>(handle as usize) };
+}
+
 #[napi]
 fn get_aggregator_signers(
     threshold: u32,
@@ -185,12 +310,23 @@ fn get_aggregator_signers(
     })
 }
 
+fn check_commitment_index_in_range(index: u32, num_shares: usize) -> Result<()> {
+    if index as usize >= num_shares {
+        return Err(Error::from_reason(format!(
+            "commitment share index {} out of range (batch has {} shares)",
+            index, num_shares
+        )));
+    }
+    Ok(())
+}
+
 #[napi]
 fn sign_partial(
     secret_key: SecretKeyWrapper,
     group_key: Buffer,
     message: Buffer,
     secret_comm_share_handle: i64,
+    index: u32,
     signers: Vec<SignerWrapper>,
 ) -> Result<PartialThresholdSigWrapper> {
     let sk: Option<IndividualSecretKey> = secret_key.into();
@@ -203,19 +339,31 @@ fn sign_partial(
     let mut secret_comm_share: Box<SecretCommitmentShareList> =
         unsafe { from_handle(secret_comm_share_handle) };
 
-    sk.sign(
-        &message_hash,
-        &gk,
-        &mut secret_comm_share,
-        0,
-        &signers
-            .into_iter()
-            .map(|v| v.into())
-            .collect::<Option<Vec<_>>>()
-            .ok_or_else(|| Error::from_reason::<String>("invalid signers".into()))?,
-    )
-    .map(|sig| sig.into())
-    .map_err(|e| Error::from_reason(format!("failed to sign message {}", e)))
+    if let Err(e) = check_commitment_index_in_range(index, secret_comm_share.commitments.len()) {
+        std::mem::forget(secret_comm_share);
+        return Err(e);
+    }
+
+    let result = sk
+        .sign(
+            &message_hash,
+            &gk,
+            &mut secret_comm_share,
+            index as usize,
+            &signers
+                .into_iter()
+                .map(|v| v.into())
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| Error::from_reason::<String>("invalid signers".into()))?,
+        )
+        .map(|sig| sig.into())
+        .map_err(|e| Error::from_reason(format!("failed to sign message {}", e)));
+
+    // `sign` marks the consumed share as used in-place; keep the rest of the
+    // precomputed batch alive so later indices can still be signed with.
+    std::mem::forget(secret_comm_share);
+
+    result
 }
 
 #[napi]
@@ -233,14 +381,26 @@ fn aggregate_signatures(
     }
     let aggregator = aggregator
         .finalize()
-        .map_err(|_| Error::from_reason::<String>("failed to finalize aggregation".into()))?;
+        .map_err(|e| misbehaving_participants_error("failed to finalize aggregation", &e))?;
     let sig = aggregator
         .aggregate()
-        .map_err(|_| Error::from_reason::<String>("failed to aggregate signatures".into()))?;
+        .map_err(|e| misbehaving_participants_error("failed to aggregate signatures", &e))?;
 
     return Ok(sig.to_ed25519().to_vec().into());
 }
 
+fn misbehaving_participants_error(
+    context: &str,
+    offenders: &std::collections::HashMap<u32, &'static str>,
+) -> Error {
+    let mut misbehaving: Vec<u32> = offenders.keys().copied().collect();
+    misbehaving.sort_unstable();
+    Error::from_reason(format!(
+        "{}. misbehaving participants: {:?}",
+        context, misbehaving
+    ))
+}
+
 #[napi]
 fn validate_signature(
     group_key: Buffer,
@@ -271,3 +431,373 @@ fn group_key_to_ed25519(group_key: Buffer) -> Result<Buffer> {
 
     return Ok(gk.to_ed25519().to_vec().into());
 }
+
+#[napi]
+fn validate_signatures_batch(items: Vec<SignatureBatchItem>) -> Result<()> {
+    let mut messages: Vec<Vec<u8>> = Vec::with_capacity(items.len());
+    let mut signatures: Vec<ed25519_dalek::Signature> = Vec::with_capacity(items.len());
+    let mut public_keys: Vec<ed25519_dalek::PublicKey> = Vec::with_capacity(items.len());
+
+    for item in &items {
+        let gk = group_key_from_buff(item.group_key.clone())
+            .ok_or_else(|| Error::from_reason::<String>("invalid group key".into()))?;
+        let gk_ed = ed25519_dalek::PublicKey::from_bytes(&gk.to_ed25519())
+            .map_err(|_| Error::from_reason::<String>("invalid group key".into()))?;
+
+        if item.signature.len() != 64 {
+            return Err(Error::from_reason::<String>(
+                "invalid signature length".into(),
+            ));
+        }
+        let mut sig = [0u8; 64];
+        sig.copy_from_slice(&item.signature);
+
+        messages.push(message_to_buffer(&item.message).to_vec());
+        signatures.push(ed25519_dalek::Signature::from(sig));
+        public_keys.push(gk_ed);
+    }
+
+    let message_refs: Vec<&[u8]> = messages.iter().map(|m| m.as_slice()).collect();
+
+    let invalid_indices = invalid_signature_indices(&message_refs, &signatures, &public_keys);
+    if invalid_indices.is_empty() {
+        return Ok(());
+    }
+
+    Err(Error::from_reason(format!(
+        "batch signature verification failed; invalid indices: {:?}",
+        invalid_indices
+    )))
+}
+
+// Falls back to per-item verification only when the batch as a whole fails,
+// so a caller can tell which signatures were bad instead of just that
+// *something* was.
+fn invalid_signature_indices(
+    messages: &[&[u8]],
+    signatures: &[ed25519_dalek::Signature],
+    public_keys: &[ed25519_dalek::PublicKey],
+) -> Vec<u32> {
+    if ed25519_dalek::verify_batch(messages, signatures, public_keys).is_ok() {
+        return Vec::new();
+    }
+
+    messages
+        .iter()
+        .zip(signatures.iter())
+        .zip(public_keys.iter())
+        .enumerate()
+        .filter_map(|(i, ((m, s), p))| match p.verify(m, s) {
+            Ok(_) => None,
+            Err(_) => Some(i as u32),
+        })
+        .collect()
+}
+
+fn lagrange_coefficient(index: u32, helper_indices: &[u32], at: u32) -> Result<Scalar> {
+    for (pos, j) in helper_indices.iter().enumerate() {
+        if helper_indices[pos + 1..].contains(j) {
+            return Err(Error::from_reason::<String>(
+                "duplicate helper index in repair set".into(),
+            ));
+        }
+    }
+    let xi = Scalar::from(index as u64);
+    let x = Scalar::from(at as u64);
+    let mut num = Scalar::one();
+    let mut den = Scalar::one();
+    for &j in helper_indices {
+        if j == index {
+            continue;
+        }
+        let xj = Scalar::from(j as u64);
+        num *= x - xj;
+        den *= xi - xj;
+    }
+    Ok(num * den.invert())
+}
+
+fn scalar_from_buff(buf: &Buffer) -> Result<Scalar> {
+    if buf.len() != 32 {
+        return Err(Error::from_reason::<String>("invalid scalar".into()));
+    }
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(buf);
+    Scalar::from_canonical_bytes(bytes)
+        .ok_or_else(|| Error::from_reason::<String>("invalid scalar".into()))
+}
+
+#[napi]
+fn repair_share_step_1(
+    helper_secret_key: SecretKeyWrapper,
+    helper_indices: Vec<u32>,
+    revealed_index: u32,
+) -> Result<Vec<RepairShareDelta>> {
+    let sk: Option<IndividualSecretKey> = helper_secret_key.into();
+    let sk = sk.ok_or_else(|| Error::from_reason::<String>("invalid secret key".into()))?;
+
+    if !helper_indices.contains(&sk.index) {
+        return Err(Error::from_reason::<String>(
+            "helper secret key index is not a member of the helper set".into(),
+        ));
+    }
+
+    let lambda = lagrange_coefficient(sk.index, &helper_indices, revealed_index)?;
+    let contribution = lambda * sk.key;
+
+    let mut rng = OsRng;
+    let mut parts = Vec::with_capacity(helper_indices.len());
+    let mut running_sum = Scalar::zero();
+    for _ in 1..helper_indices.len() {
+        let part = Scalar::random(&mut rng);
+        running_sum += part;
+        parts.push(part);
+    }
+    parts.push(contribution - running_sum);
+
+    Ok(helper_indices
+        .into_iter()
+        .zip(parts.into_iter())
+        .map(|(to_index, part)| RepairShareDelta {
+            to_index,
+            delta: part.to_bytes().to_vec().into(),
+        })
+        .collect())
+}
+
+#[napi]
+fn repair_share_step_2(received_deltas: Vec<Buffer>) -> Result<Buffer> {
+    let mut sigma = Scalar::zero();
+    for delta in &received_deltas {
+        sigma += scalar_from_buff(delta)?;
+    }
+    Ok(sigma.to_bytes().to_vec().into())
+}
+
+#[napi]
+fn repair_share_step_3(sigmas: Vec<Buffer>, revealed_index: u32) -> Result<SecretKeyWrapper> {
+    let mut key = Scalar::zero();
+    for sigma in &sigmas {
+        key += scalar_from_buff(sigma)?;
+    }
+
+    let secret_key = IndividualSecretKey {
+        index: revealed_index,
+        key,
+    };
+
+    Ok(secret_key.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Signer;
+
+    fn sample_polynomial(threshold: u32, rng: &mut OsRng) -> Vec<Scalar> {
+        (0..threshold).map(|_| Scalar::random(rng)).collect()
+    }
+
+    fn evaluate(coefficients: &[Scalar], x: Scalar) -> Scalar {
+        let mut share = Scalar::zero();
+        for coeff in coefficients.iter().rev() {
+            share = share * x + coeff;
+        }
+        share
+    }
+
+    // Mirrors keygen_with_dealer: a `threshold`-sized subset of shares must
+    // Lagrange-interpolate back to coefficients[0], the scalar the group key
+    // is derived from.
+    #[test]
+    fn keygen_with_dealer_shares_reconstruct_the_group_secret() {
+        let mut rng = OsRng;
+        let threshold = 3u32;
+        let num_sig = 5u32;
+        let coefficients = sample_polynomial(threshold, &mut rng);
+
+        let shares: Vec<(u32, Scalar)> = (1..=num_sig)
+            .map(|i| (i, evaluate(&coefficients, Scalar::from(i as u64))))
+            .collect();
+
+        let subset: Vec<u32> = shares
+            .iter()
+            .take(threshold as usize)
+            .map(|(i, _)| *i)
+            .collect();
+
+        let mut reconstructed = Scalar::zero();
+        for (i, share) in shares.iter().take(threshold as usize) {
+            reconstructed += lagrange_coefficient(*i, &subset, 0).unwrap() * share;
+        }
+
+        assert_eq!(reconstructed, coefficients[0]);
+    }
+
+    // Exercises the same additive-splitting algebra as
+    // repair_share_step_1/2/3: every helper's Lagrange-weighted contribution,
+    // split and re-summed through the helper set, must add back up to
+    // exactly the revealed participant's lost share.
+    #[test]
+    fn repair_share_round_trips_through_all_three_steps() {
+        let mut rng = OsRng;
+        let threshold = 3u32;
+        let coefficients = sample_polynomial(threshold, &mut rng);
+
+        let revealed_index = 7u32;
+        let lost_share = evaluate(&coefficients, Scalar::from(revealed_index as u64));
+
+        let helper_indices = vec![1u32, 2, 3];
+        let helper_shares: Vec<Scalar> = helper_indices
+            .iter()
+            .map(|&i| evaluate(&coefficients, Scalar::from(i as u64)))
+            .collect();
+
+        // step 1
+        let mut inbox: Vec<Vec<Scalar>> = vec![Vec::new(); helper_indices.len()];
+        for (helper_pos, &helper_index) in helper_indices.iter().enumerate() {
+            let lambda =
+                lagrange_coefficient(helper_index, &helper_indices, revealed_index).unwrap();
+            let contribution = lambda * helper_shares[helper_pos];
+
+            let mut running_sum = Scalar::zero();
+            let mut parts = Vec::with_capacity(helper_indices.len());
+            for _ in 1..helper_indices.len() {
+                let part = Scalar::random(&mut rng);
+                running_sum += part;
+                parts.push(part);
+            }
+            parts.push(contribution - running_sum);
+
+            for (to_pos, part) in parts.into_iter().enumerate() {
+                inbox[to_pos].push(part);
+            }
+        }
+
+        // step 2
+        let mut sigmas = Vec::with_capacity(helper_indices.len());
+        for received in inbox {
+            let mut sigma = Scalar::zero();
+            for part in received {
+                sigma += part;
+            }
+            sigmas.push(sigma);
+        }
+
+        // step 3
+        let mut recovered = Scalar::zero();
+        for sigma in sigmas {
+            recovered += sigma;
+        }
+
+        assert_eq!(recovered, lost_share);
+    }
+
+    #[test]
+    fn commitment_index_bounds_check_rejects_out_of_range() {
+        assert!(check_commitment_index_in_range(0, 3).is_ok());
+        assert!(check_commitment_index_in_range(2, 3).is_ok());
+        assert!(check_commitment_index_in_range(3, 3).is_err());
+        assert!(check_commitment_index_in_range(0, 0).is_err());
+    }
+
+    // sign_partial keeps the commitment-share handle alive (via
+    // mem::forget) across calls so a precomputed batch can be consumed one
+    // index at a time; this exercises exactly that borrow pattern.
+    #[test]
+    fn commitment_share_list_survives_repeated_handle_borrows() {
+        let (_public_comm, secret_comm) = generate_commitment_share_lists(&mut OsRng, 1, 3);
+        let handle = into_boxed_handle(secret_comm);
+
+        for _ in 0..3 {
+            let boxed: Box<SecretCommitmentShareList> = unsafe { from_handle(handle) };
+            assert_eq!(boxed.commitments.len(), 3);
+            std::mem::forget(boxed);
+        }
+    }
+
+    #[test]
+    fn misbehaving_participants_are_reported_sorted() {
+        let mut offenders: std::collections::HashMap<u32, &'static str> =
+            std::collections::HashMap::new();
+        offenders.insert(5, "invalid partial signature");
+        offenders.insert(2, "missing commitment");
+
+        let err = misbehaving_participants_error("failed to aggregate signatures", &offenders);
+        let message = err.to_string();
+
+        assert!(message.contains("failed to aggregate signatures"));
+        assert!(message.contains("[2, 5]"));
+    }
+
+    // validate_signatures_batch only reports indices once the cheap
+    // verify_batch fast path fails; exercise both the all-valid case and the
+    // fallback that pinpoints which entry was bad.
+    #[test]
+    fn invalid_signature_indices_is_empty_for_a_valid_batch() {
+        let mut rng = OsRng;
+        let keypair_a = ed25519_dalek::Keypair::generate(&mut rng);
+        let keypair_b = ed25519_dalek::Keypair::generate(&mut rng);
+
+        let message_a = b"first message".to_vec();
+        let message_b = b"second message".to_vec();
+        let sig_a = keypair_a.sign(&message_a);
+        let sig_b = keypair_b.sign(&message_b);
+
+        let messages: Vec<&[u8]> = vec![&message_a, &message_b];
+        let signatures = vec![sig_a, sig_b];
+        let public_keys = vec![keypair_a.public, keypair_b.public];
+
+        assert!(invalid_signature_indices(&messages, &signatures, &public_keys).is_empty());
+    }
+
+    #[test]
+    fn invalid_signature_indices_reports_the_bad_entry() {
+        let mut rng = OsRng;
+        let keypair_a = ed25519_dalek::Keypair::generate(&mut rng);
+        let keypair_b = ed25519_dalek::Keypair::generate(&mut rng);
+
+        let message_a = b"first message".to_vec();
+        let message_b = b"second message".to_vec();
+        let sig_a = keypair_a.sign(&message_a);
+        // Signed with the wrong key, so it won't verify against keypair_b's
+        // public key below.
+        let bad_sig_b = keypair_a.sign(&message_b);
+
+        let messages: Vec<&[u8]> = vec![&message_a, &message_b];
+        let signatures = vec![sig_a, bad_sig_b];
+        let public_keys = vec![keypair_a.public, keypair_b.public];
+
+        assert_eq!(
+            invalid_signature_indices(&messages, &signatures, &public_keys),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn invalid_signature_indices_is_empty_for_an_empty_batch() {
+        let messages: Vec<&[u8]> = Vec::new();
+        let signatures: Vec<ed25519_dalek::Signature> = Vec::new();
+        let public_keys: Vec<ed25519_dalek::PublicKey> = Vec::new();
+
+        assert!(invalid_signature_indices(&messages, &signatures, &public_keys).is_empty());
+    }
+
+    // A checkpoint is only useful if reloading it reproduces the exact same
+    // state; round-trip the bytes twice and compare rather than trusting
+    // that deserialize_coefficients_state "looks right".
+    #[cfg(feature = "frost-serde")]
+    #[test]
+    fn coefficients_checkpoint_round_trips_through_serialize_and_deserialize() {
+        let params = Parameters { n: 3, t: 2 };
+        let (_participant, coefficients) = Participant::new(&params, 1);
+
+        let handle = into_boxed_handle(coefficients);
+        let bytes = serialize_coefficients_state(handle).unwrap();
+
+        let restored_handle = deserialize_coefficients_state(bytes.clone()).unwrap();
+        let bytes_again = serialize_coefficients_state(restored_handle).unwrap();
+
+        assert_eq!(bytes.as_ref(), bytes_again.as_ref());
+    }
+}
@@ -0,0 +1,28 @@
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+#[napi(object)]
+pub struct SignatureBatchItem {
+    pub group_key: Buffer,
+    pub signature: Buffer,
+    pub message: Buffer,
+}
+
+#[napi(object)]
+pub struct DealerShareRes {
+    pub index: u32,
+    pub sk: SecretKeyWrapper,
+    pub pubk: PublicKeyWrapper,
+}
+
+#[napi(object)]
+pub struct DealerKeygenRes {
+    pub gk: Buffer,
+    pub shares: Vec<DealerShareRes>,
+}
+
+#[napi(object)]
+pub struct RepairShareDelta {
+    pub to_index: u32,
+    pub delta: Buffer,
+}